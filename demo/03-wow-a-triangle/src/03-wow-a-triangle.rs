@@ -13,62 +13,277 @@
 // limitations under the License.
 
 extern crate cgmath;
+extern crate freetype;
 extern crate gl;
 extern crate glfw;
 extern crate native;
+#[cfg(feature = "sdl2-backend")]
+extern crate sdl2;
+extern crate stb_image;
 
+use backend::{Backend, Window};
+use hud::Hud;
 use gl::types::{GLchar, GLenum, GLfloat};
 use gl::types::{GLint, GLsizei, GLsizeiptr, GLuint, GLvoid};
-use glfw::Context;
-use cgmath::angle::rad;
+use cgmath::angle::{deg, rad};
 use cgmath::array::Array2;
-use cgmath::matrix::{ToMatrix4};
+use cgmath::matrix::{Matrix4, ToMatrix4};
+use cgmath::projection::perspective;
 use cgmath::quaternion::Quaternion;
 use cgmath::rotation::Rotation3;
+use std::io::File;
 use std::mem;
 use std::ptr;
 
-static VERTEX_DATA: [GLfloat, ..18] = [
-     0.0,  0.5,    0.0,  0.0,  1.0,  1.0,
-     0.5, -0.5,    0.0,  1.0,  0.0,  1.0,
-    -0.5, -0.5,    1.0,  0.0,  0.0,  1.0,
+mod backend;
+mod bench;
+mod hud;
+
+static FONT_PATH: &'static str = "demo/03-wow-a-triangle/assets/DejaVuSans.ttf";
+
+#[cfg(not(feature = "sdl2-backend"))]
+type ActiveBackend = backend::glfw_backend::GlfwFactory;
+#[cfg(feature = "sdl2-backend")]
+type ActiveBackend = backend::sdl2_backend::Sdl2Factory;
+
+static TEXTURE_PATH: &'static str = "demo/03-wow-a-triangle/assets/wall.png";
+static VERTEX_SHADER_PATH: &'static str = "demo/03-wow-a-triangle/shaders/cube.vert";
+static FRAGMENT_SHADER_PATH: &'static str = "demo/03-wow-a-triangle/shaders/cube.frag";
+
+// position (xyz) | color (rgba) | texcoord (uv) | normal (xyz), a unit cube
+// built from six quads (two triangles each), wound counter-clockwise when
+// viewed from outside.
+static VERTEX_DATA: [GLfloat, ..432] = [
+    // back (-z)
+    -0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 0.0,    0.0,  0.0, -1.0,
+     0.5,  0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 1.0,    0.0,  0.0, -1.0,
+     0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,    0.0,  0.0, -1.0,
+     0.5,  0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 1.0,    0.0,  0.0, -1.0,
+    -0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 0.0,    0.0,  0.0, -1.0,
+    -0.5,  0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,    0.0,  0.0, -1.0,
+
+    // front (+z)
+    -0.5, -0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 0.0,    0.0,  0.0,  1.0,
+     0.5, -0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,    0.0,  0.0,  1.0,
+     0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 1.0,    0.0,  0.0,  1.0,
+     0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 1.0,    0.0,  0.0,  1.0,
+    -0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,    0.0,  0.0,  1.0,
+    -0.5, -0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 0.0,    0.0,  0.0,  1.0,
+
+    // left (-x)
+    -0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,   -1.0,  0.0,  0.0,
+    -0.5,  0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 1.0,   -1.0,  0.0,  0.0,
+    -0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,   -1.0,  0.0,  0.0,
+    -0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,   -1.0,  0.0,  0.0,
+    -0.5, -0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 0.0,   -1.0,  0.0,  0.0,
+    -0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,   -1.0,  0.0,  0.0,
+
+    // right (+x)
+     0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,    1.0,  0.0,  0.0,
+     0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,    1.0,  0.0,  0.0,
+     0.5,  0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 1.0,    1.0,  0.0,  0.0,
+     0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,    1.0,  0.0,  0.0,
+     0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,    1.0,  0.0,  0.0,
+     0.5, -0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 0.0,    1.0,  0.0,  0.0,
+
+    // bottom (-y)
+    -0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,    0.0, -1.0,  0.0,
+     0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 1.0,    0.0, -1.0,  0.0,
+     0.5, -0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,    0.0, -1.0,  0.0,
+     0.5, -0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,    0.0, -1.0,  0.0,
+    -0.5, -0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 0.0,    0.0, -1.0,  0.0,
+    -0.5, -0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,    0.0, -1.0,  0.0,
+
+    // top (+y)
+    -0.5,  0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,    0.0,  1.0,  0.0,
+     0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,    0.0,  1.0,  0.0,
+     0.5,  0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 1.0,    0.0,  1.0,  0.0,
+     0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   1.0, 0.0,    0.0,  1.0,  0.0,
+    -0.5,  0.5, -0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 1.0,    0.0,  1.0,  0.0,
+    -0.5,  0.5,  0.5,   1.0, 1.0, 1.0, 1.0,   0.0, 0.0,    0.0,  1.0,  0.0,
 ];
 
-static VERTEX_SHADER_SRC: &'static [u8] = b"
-    #version 150
-    uniform mat4 modelview;
-    in vec2 position;
-    in vec4 color;
-    out vec4 in_color;
-    void main() {
-       gl_Position = modelview * vec4(position, 0.0, 1.0);
-       in_color = color;
-    }
-";
-
-static FRAGMENT_SHADER_SRC: &'static [u8] = b"
-    #version 150
-    in vec4 in_color;
-    out vec4 out_color;
-    void main() {
-       out_color = in_color;
-    }
-";
-
-fn compile_shader(src: &[u8], ty: GLenum) -> GLuint {
+fn compile_shader(src: &[u8], ty: GLenum) -> Result<GLuint, String> {
     let shader = gl::CreateShader(ty);
     let len = src.len() as GLint;
     unsafe { gl::ShaderSource(shader, 1, &(src.as_ptr() as *GLchar), &len) };
     gl::CompileShader(shader);
-    shader
+
+    let mut status = gl::FALSE as GLint;
+    unsafe { gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status) };
+
+    if status != (gl::TRUE as GLint) {
+        let mut len = 0;
+        unsafe { gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len) };
+        let mut buf = Vec::from_elem(len as uint, 0u8);
+        unsafe {
+            gl::GetShaderInfoLog(shader, len, ptr::mut_null(),
+                                 buf.as_mut_ptr() as *mut GLchar);
+        }
+        buf.pop(); // drop the trailing NUL
+        gl::DeleteShader(shader);
+        Err(String::from_utf8(buf).unwrap_or("shader log was not valid UTF-8".to_string()))
+    } else {
+        Ok(shader)
+    }
 }
 
-fn link_program(vs: GLuint, fs: GLuint) -> GLuint {
+fn link_program(vs: GLuint, fs: GLuint) -> Result<GLuint, String> {
     let program = gl::CreateProgram();
     gl::AttachShader(program, vs);
     gl::AttachShader(program, fs);
     gl::LinkProgram(program);
-    program
+
+    let mut status = gl::FALSE as GLint;
+    unsafe { gl::GetProgramiv(program, gl::LINK_STATUS, &mut status) };
+
+    if status != (gl::TRUE as GLint) {
+        let mut len = 0;
+        unsafe { gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len) };
+        let mut buf = Vec::from_elem(len as uint, 0u8);
+        unsafe {
+            gl::GetProgramInfoLog(program, len, ptr::mut_null(),
+                                  buf.as_mut_ptr() as *mut GLchar);
+        }
+        buf.pop(); // drop the trailing NUL
+        Err(String::from_utf8(buf).unwrap_or("program log was not valid UTF-8".to_string()))
+    } else {
+        Ok(program)
+    }
+}
+
+// Uploads a single opaque white texel. Used when `load_texture` can't load its
+// image, so the textured-cube path still runs (just untinted) instead of
+// refusing to start.
+fn white_texture() -> GLuint {
+    let pixel: [u8, ..4] = [255, 255, 255, 255];
+    let mut texture = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+        gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA8 as GLint, 1, 1, 0,
+                       gl::RGBA, gl::UNSIGNED_BYTE, pixel.as_ptr() as *GLvoid);
+    }
+    texture
+}
+
+fn load_texture(path: &str) -> GLuint {
+    let img = match stb_image::image::load(path) {
+        stb_image::image::LoadResult::ImageU8(img) => img,
+        stb_image::image::LoadResult::ImageF32(_) => {
+            println!("{}: expected an 8-bit image, got floating-point data; using a blank texture", path);
+            return white_texture();
+        }
+        stb_image::image::LoadResult::Error(msg) => {
+            println!("failed to load texture {}: {}; using a blank texture", path, msg);
+            return white_texture();
+        }
+    };
+
+    let format = if img.depth == 4 { gl::RGBA } else { gl::RGB };
+
+    let mut texture = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+        gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA8 as GLint,
+                       img.width as GLsizei, img.height as GLsizei, 0,
+                       format, gl::UNSIGNED_BYTE, img.data.as_ptr() as *GLvoid);
+    }
+    texture
+}
+
+fn read_file(path: &str) -> Vec<u8> {
+    File::open(&Path::new(path)).read_to_end()
+        .unwrap_or_else(|e| fail!("failed to read {}: {}", path, e))
+}
+
+fn mtime(path: &str) -> u64 {
+    std::io::fs::stat(&Path::new(path))
+        .unwrap_or_else(|e| fail!("failed to stat {}: {}", path, e))
+        .modified
+}
+
+// Uniform and attribute locations, re-resolved every time the program is
+// rebuilt since a reload can renumber them.
+struct Locations {
+    modelview: GLint,
+    projection: GLint,
+    tex: GLint,
+    light_position: GLint,
+    eye: GLint,
+    ambient_factor: GLint,
+    shininess: GLint,
+    position: GLint,
+    color: GLint,
+    texcoord: GLint,
+    normal: GLint,
+}
+
+fn resolve_locations(program: GLuint) -> Locations {
+    unsafe { "out_color".with_c_str(|ptr| gl::BindFragDataLocation(program, 0, ptr)) };
+
+    let get_attrib = |s: &str| -> GLint { s.with_c_str(|ptr| unsafe { gl::GetAttribLocation(program, ptr) }) };
+    let get_uniform = |s: &str| -> GLint { s.with_c_str(|ptr| unsafe { gl::GetUniformLocation(program, ptr) }) };
+
+    Locations {
+        modelview: get_uniform("modelview"),
+        projection: get_uniform("projection"),
+        tex: get_uniform("tex"),
+        light_position: get_uniform("light_position"),
+        eye: get_uniform("eye"),
+        ambient_factor: get_uniform("ambient_factor"),
+        shininess: get_uniform("shininess"),
+        position: get_attrib("position"),
+        color: get_attrib("color"),
+        texcoord: get_attrib("texcoord"),
+        normal: get_attrib("normal"),
+    }
+}
+
+// Re-specify the VAO's vertex layout for (possibly new) attribute locations.
+fn bind_vertex_layout(locs: &Locations, sizeof_float: uint) {
+    unsafe {
+        gl::EnableVertexAttribArray(locs.position as GLuint);
+        gl::EnableVertexAttribArray(locs.color as GLuint);
+        gl::EnableVertexAttribArray(locs.texcoord as GLuint);
+        gl::EnableVertexAttribArray(locs.normal as GLuint);
+        let stride = 12 * sizeof_float as GLsizei;
+        gl::VertexAttribPointer(locs.position as GLuint, 3, gl::FLOAT,
+                                gl::FALSE, stride, ptr::null());
+        gl::VertexAttribPointer(locs.color as GLuint, 4, gl::FLOAT,
+                                gl::FALSE, stride, ptr::null().offset(3 * sizeof_float as int));
+        gl::VertexAttribPointer(locs.texcoord as GLuint, 2, gl::FLOAT,
+                                gl::FALSE, stride, ptr::null().offset(7 * sizeof_float as int));
+        gl::VertexAttribPointer(locs.normal as GLuint, 3, gl::FLOAT,
+                                gl::FALSE, stride, ptr::null().offset(9 * sizeof_float as int));
+    }
+}
+
+// Compile and link `vs_path`/`fs_path` into a fresh program. On any failure,
+// any shader objects that were created are cleaned up before returning.
+fn build_program(vs_path: &str, fs_path: &str) -> Result<(GLuint, GLuint, GLuint), String> {
+    let vs = try!(compile_shader(read_file(vs_path).as_slice(), gl::VERTEX_SHADER));
+    let fs = match compile_shader(read_file(fs_path).as_slice(), gl::FRAGMENT_SHADER) {
+        Ok(fs) => fs,
+        Err(msg) => { gl::DeleteShader(vs); return Err(msg); }
+    };
+    match link_program(vs, fs) {
+        Ok(program) => Ok((program, vs, fs)),
+        Err(msg) => {
+            gl::DeleteShader(vs);
+            gl::DeleteShader(fs);
+            Err(msg)
+        }
+    }
 }
 
 #[start]
@@ -77,29 +292,33 @@ fn start(argc: int, argv: **u8) -> int {
 }
 
 fn main() {
-    // initialise context (handle can't be moved between threads)
-    let glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-
-    // Choose a GL profile that is compatible with OS X 10.7+
-    glfw.window_hint(glfw::ContextVersion(3, 2));
-    glfw.window_hint(glfw::OpenglForwardCompat(true));
-    glfw.window_hint(glfw::OpenglProfile(glfw::OpenGlCoreProfile));
-
-    let (window, events) = glfw.create_window(800, 600, "Spiiiin", glfw::Windowed)
-        .expect("Failed to create GLFW window.");
-
-    window.set_key_polling(true);
+    // `--bench` skips the cube demo and runs the instanced-rendering
+    // throughput test instead, with the instance count set via
+    // `--instances=N`; `--naive` makes it issue one DrawArrays call per
+    // instance for comparison.
+    let args = std::os::args();
+    if args.iter().any(|a| a.as_slice() == "--bench") {
+        let instance_count = args.iter()
+            .find(|a| a.as_slice().starts_with("--instances="))
+            .and_then(|a| from_str(a.as_slice().slice_from(12)))
+            .unwrap_or(bench::DEFAULT_INSTANCE_COUNT);
+        let naive = args.iter().any(|a| a.as_slice() == "--naive");
+        bench::run(instance_count, naive);
+        return;
+    }
 
-    // It is essential to make the context current before calling `gl::load_with`.
-    window.make_current();
+    // Create the window and its GL context; the active backend decides
+    // whether that means GLFW or SDL2.
+    let mut window = ActiveBackend::create("Spiiiin", 800, 600);
 
     // Load the OpenGL function pointers
-    gl::load_with(|s| glfw.get_proc_address(s));
+    gl::load_with(|s| window.get_proc_address(s));
 
     // Create GLSL shaders
-    let vs = compile_shader(VERTEX_SHADER_SRC, gl::VERTEX_SHADER);
-    let fs = compile_shader(FRAGMENT_SHADER_SRC, gl::FRAGMENT_SHADER);
-    let program = link_program(vs, fs);
+    let (mut program, mut vs, mut fs) = build_program(VERTEX_SHADER_PATH, FRAGMENT_SHADER_PATH)
+        .unwrap_or_else(|log| fail!("shader program failed to build:\n{}", log));
+    let mut vs_mtime = mtime(VERTEX_SHADER_PATH);
+    let mut fs_mtime = mtime(FRAGMENT_SHADER_PATH);
 
     let mut vao = 0;
     let mut vbo = 0;
@@ -123,26 +342,40 @@ fn main() {
     // Use the shader program
     gl::UseProgram(program);
 
-    // Attributes
-    unsafe {
-        "out_color".with_c_str(|ptr| gl::BindFragDataLocation(program, 0, ptr));
-
-        let get_attrib_location = |s: &str| -> GLint { s.with_c_str(|ptr| gl::GetAttribLocation(program, ptr)) };
-        let pos_attr = get_attrib_location("position");
-        let color_attr = get_attrib_location("color");
+    let mut locs = resolve_locations(program);
+    bind_vertex_layout(&locs, sizeof_float);
 
-        // Specify the layout of the vertex data
-        gl::EnableVertexAttribArray(pos_attr as GLuint);
-        gl::EnableVertexAttribArray(color_attr as GLuint);
-        let stride = 6 * sizeof_float as GLsizei;
-        gl::VertexAttribPointer(pos_attr as GLuint, 2, gl::FLOAT,
-                                gl::FALSE, stride, ptr::null());
-        gl::VertexAttribPointer(color_attr as GLuint, 4, gl::FLOAT,
-                                gl::FALSE, stride, ptr::null().offset(2 * sizeof_float as int));
+    // Load the texture and bind it to texture unit 0
+    let texture = load_texture(TEXTURE_PATH);
+    unsafe {
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
     }
-
-    let get_uniform = |s: &str| -> GLint { s.with_c_str(|ptr| unsafe { gl::GetUniformLocation(program, ptr) }) };
-    let modelview = get_uniform("modelview");
+    gl::Uniform1i(locs.tex, 0);
+
+    // Perspective projection for the fixed 800x600 window (there's no
+    // resize handling, so this is computed once up front).
+    let projection = perspective(deg(45.0 as GLfloat), 800.0 / 600.0, 0.1, 100.0);
+    uniform_matrix4(locs.projection, &projection);
+
+    // Light and eye live in the same space as the cube (there is no
+    // separate view transform, so "eye" is just the origin)
+    gl::Uniform3f(locs.light_position, 2.0, 2.0, 2.0);
+    gl::Uniform3f(locs.eye, 0.0, 0.0, 0.0);
+    gl::Uniform1f(locs.ambient_factor, 0.1);
+    gl::Uniform1f(locs.shininess, 32.0);
+
+    gl::Enable(gl::DEPTH_TEST);
+
+    // The HUD is optional: if the font asset can't be loaded, run without
+    // it instead of aborting the whole demo.
+    let mut hud = match Hud::new(FONT_PATH, 18) {
+        Ok(hud) => Some(hud),
+        Err(log) => {
+            println!("HUD disabled, {}", log);
+            None
+        }
+    };
 
     let mut quat = Quaternion::identity();
     let mut velx: GLfloat = 0.0;
@@ -151,12 +384,58 @@ fn main() {
 
     while !window.should_close() {
         // Poll and handle events
-        glfw.poll_events();
-        handle_events(&window, &events);
+        for event in window.poll_events().into_iter() {
+            handle_event(&mut window, event);
+        }
+
+        // Hot-reload the shaders if either source file has changed on disk
+        let new_vs_mtime = mtime(VERTEX_SHADER_PATH);
+        let new_fs_mtime = mtime(FRAGMENT_SHADER_PATH);
+        if new_vs_mtime != vs_mtime || new_fs_mtime != fs_mtime {
+            vs_mtime = new_vs_mtime;
+            fs_mtime = new_fs_mtime;
+
+            match build_program(VERTEX_SHADER_PATH, FRAGMENT_SHADER_PATH) {
+                Ok((new_program, new_vs, new_fs)) => {
+                    gl::DeleteProgram(program);
+                    gl::DeleteShader(vs);
+                    gl::DeleteShader(fs);
+                    program = new_program;
+                    vs = new_vs;
+                    fs = new_fs;
+
+                    gl::UseProgram(program);
+                    gl::BindVertexArray(vao);
+                    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+                    locs = resolve_locations(program);
+                    bind_vertex_layout(&locs, sizeof_float);
+                    gl::Uniform1i(locs.tex, 0);
+                    uniform_matrix4(locs.projection, &projection);
+                    gl::Uniform3f(locs.light_position, 2.0, 2.0, 2.0);
+                    gl::Uniform3f(locs.eye, 0.0, 0.0, 0.0);
+                    gl::Uniform1f(locs.ambient_factor, 0.1);
+                    gl::Uniform1f(locs.shininess, 32.0);
+
+                    println!("reloaded shaders");
+                }
+                Err(log) => {
+                    println!("shader reload failed, keeping previous program:\n{}", log);
+                }
+            }
+        }
 
         // Clear the screen to a nice black
         gl::ClearColor(0.0, 0.0, 0.0, 1.0);
-        gl::Clear(gl::COLOR_BUFFER_BIT);
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+        // Hud::draw_text left its own program/VAO/texture bound last frame,
+        // so re-bind the cube's before touching its uniforms or drawing it.
+        gl::UseProgram(program);
+        gl::BindVertexArray(vao);
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+        }
 
         // Rotate
         {
@@ -166,11 +445,22 @@ fn main() {
             quat = quat.mul_q(&Rotation3::from_angle_x(rad(velx)));
             quat = quat.mul_q(&Rotation3::from_angle_y(rad(vely)));
             quat = quat.mul_q(&Rotation3::from_angle_z(rad(velz)));
-            uniform_quaternion(modelview, &quat);
+
+            // Rotate the cube about its own centre, then push it out in front
+            // of the (fixed) eye
+            let mut model = quat.to_matrix4();
+            model.w.z = -3.0;
+            uniform_matrix4(locs.modelview, &model);
         }
 
-        // Draw a triangle from the 3 vertices
-        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        // Draw the lit cube (six faces, two triangles each)
+        gl::DrawArrays(gl::TRIANGLES, 0, 36);
+
+        // Overlay the HUD on top of the scene, if it's available
+        if let Some(ref mut hud) = hud {
+            let velocity_text = format!("vx {:.3}  vy {:.3}  vz {:.3}", velx, vely, velz);
+            hud.draw_text(velocity_text.as_slice(), 10.0, 10.0, 800, 600);
+        }
 
         // Swap buffers
         window.swap_buffers();
@@ -181,25 +471,23 @@ fn main() {
     gl::DeleteShader(fs);
     gl::DeleteShader(vs);
     unsafe {
+        gl::DeleteTextures(1, &texture);
         gl::DeleteBuffers(1, &vbo);
         gl::DeleteVertexArrays(1, &vao);
     }
 }
 
-fn uniform_quaternion(location: GLint, q: &Quaternion<GLfloat>) {
-    let mat = q.to_matrix4();
+fn uniform_matrix4(location: GLint, mat: &Matrix4<GLfloat>) {
     unsafe {
         gl::UniformMatrix4fv(location, 1, gl::FALSE, mat.ptr());
     }
 }
 
-fn handle_events(window: &glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
-    for (_, event) in glfw::flush_messages(events) {
-        match event {
-            glfw::KeyEvent(glfw::KeyEscape, _, glfw::Press, _) => {
-                window.set_should_close(true)
-            },
-            _ => {},
-        }
+fn handle_event<W: Window>(window: &mut W, event: backend::WindowEvent) {
+    match event {
+        backend::WindowEvent::Key(backend::Key::Escape, backend::Action::Press) => {
+            window.set_should_close(true)
+        },
+        _ => {},
     }
 }
@@ -0,0 +1,83 @@
+// Copyright 2014 Brendan Zabarauskas.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use gl::types::GLvoid;
+use glfw;
+use glfw::Context;
+use super::{Action, Backend, Key, Window, WindowEvent};
+
+pub struct GlfwBackend {
+    glfw: glfw::Glfw,
+    window: glfw::Window,
+    events: Receiver<(f64, glfw::WindowEvent)>,
+}
+
+impl Window for GlfwBackend {
+    fn poll_events(&mut self) -> Vec<WindowEvent> {
+        self.glfw.poll_events();
+
+        let mut out = Vec::new();
+        for (_, event) in glfw::flush_messages(&self.events) {
+            match event {
+                glfw::KeyEvent(glfw::KeyEscape, _, glfw::Press, _) =>
+                    out.push(WindowEvent::Key(Key::Escape, Action::Press)),
+                glfw::KeyEvent(glfw::KeyEscape, _, glfw::Release, _) =>
+                    out.push(WindowEvent::Key(Key::Escape, Action::Release)),
+                _ => {},
+            }
+        }
+        out
+    }
+
+    fn swap_buffers(&mut self) {
+        self.window.swap_buffers();
+    }
+
+    fn should_close(&self) -> bool {
+        self.window.should_close()
+    }
+
+    fn set_should_close(&mut self, value: bool) {
+        self.window.set_should_close(value);
+    }
+
+    fn get_proc_address(&self, name: &str) -> *const GLvoid {
+        self.glfw.get_proc_address(name) as *const GLvoid
+    }
+}
+
+pub struct GlfwFactory;
+
+impl Backend for GlfwFactory {
+    type Handle = GlfwBackend;
+
+    fn create(title: &str, width: u32, height: u32) -> GlfwBackend {
+        let glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+
+        // Choose a GL profile that is compatible with OS X 10.7+
+        glfw.window_hint(glfw::ContextVersion(3, 2));
+        glfw.window_hint(glfw::OpenglForwardCompat(true));
+        glfw.window_hint(glfw::OpenglProfile(glfw::OpenGlCoreProfile));
+
+        let (mut window, events) = glfw.create_window(width, height, title, glfw::Windowed)
+            .expect("Failed to create GLFW window.");
+
+        window.set_key_polling(true);
+
+        // It is essential to make the context current before calling `gl::load_with`.
+        window.make_current();
+
+        GlfwBackend { glfw: glfw, window: window, events: events }
+    }
+}
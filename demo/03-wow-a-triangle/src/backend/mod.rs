@@ -0,0 +1,55 @@
+// Copyright 2014 Brendan Zabarauskas.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Windowing backends. `main` drives the render loop purely through the
+//! `Window` trait below, so swapping which module builds `ActiveBackend`
+//! is enough to move the demo from GLFW to SDL2.
+
+use gl::types::GLvoid;
+
+pub mod glfw_backend;
+#[cfg(feature = "sdl2-backend")]
+pub mod sdl2_backend;
+
+/// A key relevant to the demo (currently just enough to quit).
+pub enum Key {
+    Escape,
+}
+
+pub enum Action {
+    Press,
+    Release,
+}
+
+pub enum WindowEvent {
+    Key(Key, Action),
+}
+
+/// A live window with a current GL context, abstracted over whichever
+/// library created it.
+pub trait Window {
+    fn poll_events(&mut self) -> Vec<WindowEvent>;
+    fn swap_buffers(&mut self);
+    fn should_close(&self) -> bool;
+    fn set_should_close(&mut self, value: bool);
+    fn get_proc_address(&self, name: &str) -> *const GLvoid;
+}
+
+/// Creates a `Window`, requesting a 3.2 core, forward-compatible GL context
+/// (works on OS X 10.7+ as well as Linux).
+pub trait Backend {
+    type Handle: Window;
+
+    fn create(title: &str, width: u32, height: u32) -> Self::Handle;
+}
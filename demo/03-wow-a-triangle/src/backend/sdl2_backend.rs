@@ -0,0 +1,94 @@
+// Copyright 2014 Brendan Zabarauskas.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use gl::types::GLvoid;
+use sdl2;
+use sdl2::keyboard::Keycode;
+use super::{Action, Backend, Key, Window, WindowEvent};
+
+pub struct Sdl2Backend {
+    window: sdl2::video::Window,
+    // Kept alive only to keep the GL context current; never read directly.
+    _gl_context: sdl2::video::GLContext,
+    event_pump: sdl2::EventPump,
+    should_close: bool,
+}
+
+impl Window for Sdl2Backend {
+    fn poll_events(&mut self) -> Vec<WindowEvent> {
+        let mut out = Vec::new();
+        for event in self.event_pump.poll_iter() {
+            match event {
+                sdl2::event::Event::Quit { .. } => self.should_close = true,
+                sdl2::event::Event::KeyDown { keycode: Some(Keycode::Escape), .. } =>
+                    out.push(WindowEvent::Key(Key::Escape, Action::Press)),
+                sdl2::event::Event::KeyUp { keycode: Some(Keycode::Escape), .. } =>
+                    out.push(WindowEvent::Key(Key::Escape, Action::Release)),
+                _ => {},
+            }
+        }
+        out
+    }
+
+    fn swap_buffers(&mut self) {
+        self.window.gl_swap_window();
+    }
+
+    fn should_close(&self) -> bool {
+        self.should_close
+    }
+
+    fn set_should_close(&mut self, value: bool) {
+        self.should_close = value;
+    }
+
+    fn get_proc_address(&self, name: &str) -> *const GLvoid {
+        self.window.subsystem().gl_get_proc_address(name) as *const GLvoid
+    }
+}
+
+pub struct Sdl2Factory;
+
+impl Backend for Sdl2Factory {
+    type Handle = Sdl2Backend;
+
+    fn create(title: &str, width: u32, height: u32) -> Sdl2Backend {
+        let sdl_context = sdl2::init().unwrap();
+        let video = sdl_context.video().unwrap();
+
+        // Choose a GL profile that is compatible with OS X 10.7+
+        let gl_attr = video.gl_attr();
+        gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
+        gl_attr.set_context_version(3, 2);
+        gl_attr.set_context_flags().forward_compatible().set();
+
+        let window = video.window(title, width, height)
+            .opengl()
+            .position_centered()
+            .build()
+            .expect("Failed to create SDL2 window.");
+
+        let gl_context = window.gl_create_context().expect("Failed to create GL context.");
+        window.gl_make_current(&gl_context).unwrap();
+
+        let event_pump = sdl_context.event_pump().unwrap();
+
+        Sdl2Backend {
+            window: window,
+            _gl_context: gl_context,
+            event_pump: event_pump,
+            should_close: false,
+        }
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2014 Brendan Zabarauskas.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instanced-rendering stress test, reachable with `--bench` on the command
+//! line. Draws `instance_count` independently rotating triangles, laid out
+//! on a spiral so they don't overlap, and prints the measured frame rate so
+//! the binding overhead of one `glDrawArraysInstanced` call per frame can be
+//! compared against `--naive`'s one `glDrawArrays` call per instance.
+
+extern crate time;
+
+use ActiveBackend;
+use backend;
+use backend::{Backend, Window};
+use gl;
+use gl::types::{GLfloat, GLint, GLsizei, GLsizeiptr, GLuint, GLvoid};
+use super::{compile_shader, link_program};
+use std::mem;
+use std::ptr;
+
+pub static DEFAULT_INSTANCE_COUNT: uint = 1000;
+
+// position (xy) | color (rgb), a small triangle shared by every instance
+static TRIANGLE_DATA: [GLfloat, ..15] = [
+     0.0,  0.02,   1.0, 1.0, 1.0,
+     0.015, -0.015, 1.0, 1.0, 1.0,
+    -0.015, -0.015, 1.0, 1.0, 1.0,
+];
+
+static VERTEX_SHADER_SRC: &'static [u8] = b"
+    #version 150
+    uniform float time;
+    in vec2 position;
+    in vec3 color;
+    in vec2 instance_offset;
+    in float instance_angle;
+    out vec3 in_color;
+    void main() {
+       float angle = instance_angle + time;
+       float s = sin(angle);
+       float c = cos(angle);
+       vec2 rotated = vec2(position.x * c - position.y * s,
+                            position.x * s + position.y * c);
+       gl_Position = vec4(rotated + instance_offset, 0.0, 1.0);
+       in_color = color;
+    }
+";
+
+static FRAGMENT_SHADER_SRC: &'static [u8] = b"
+    #version 150
+    in vec3 in_color;
+    out vec4 out_color;
+    void main() {
+       out_color = vec4(in_color, 1.0);
+    }
+";
+
+// Per-instance (offset.x, offset.y, base_angle), laid out on a spiral so
+// instances fan out across the viewport instead of stacking on top of
+// each other.
+fn instance_data(instance_count: uint) -> Vec<GLfloat> {
+    let golden_angle: GLfloat = 2.39996;
+    let mut data = Vec::with_capacity(instance_count * 3);
+    for i in range(0u, instance_count) {
+        let t = i as GLfloat;
+        let radius = 0.9 * (t / instance_count as GLfloat).sqrt();
+        let theta = t * golden_angle;
+        data.push(radius * theta.cos());
+        data.push(radius * theta.sin());
+        data.push(theta);
+    }
+    data
+}
+
+/// Runs the stress test until the window is closed, printing one FPS
+/// measurement per second.
+pub fn run(instance_count: uint, naive: bool) {
+    let mut window = ActiveBackend::create("Instanced triangles", 800, 600);
+    gl::load_with(|s| window.get_proc_address(s));
+
+    let vs = compile_shader(VERTEX_SHADER_SRC, gl::VERTEX_SHADER)
+        .unwrap_or_else(|log| fail!("bench vertex shader failed to compile:\n{}", log));
+    let fs = compile_shader(FRAGMENT_SHADER_SRC, gl::FRAGMENT_SHADER)
+        .unwrap_or_else(|log| fail!("bench fragment shader failed to compile:\n{}", log));
+    let program = link_program(vs, fs)
+        .unwrap_or_else(|log| fail!("bench program failed to link:\n{}", log));
+    gl::UseProgram(program);
+
+    let mut vao = 0;
+    unsafe { gl::GenVertexArrays(1, &mut vao) };
+    gl::BindVertexArray(vao);
+
+    let sizeof_float = mem::size_of::<GLfloat>();
+
+    let mut triangle_vbo = 0;
+    unsafe {
+        gl::GenBuffers(1, &mut triangle_vbo);
+        gl::BindBuffer(gl::ARRAY_BUFFER, triangle_vbo);
+        gl::BufferData(gl::ARRAY_BUFFER,
+                       (TRIANGLE_DATA.len() * sizeof_float) as GLsizeiptr,
+                       TRIANGLE_DATA.as_ptr() as *GLvoid,
+                       gl::STATIC_DRAW);
+    }
+
+    let get_attrib = |s: &str| -> GLint { s.with_c_str(|ptr| unsafe { gl::GetAttribLocation(program, ptr) }) };
+    let get_uniform = |s: &str| -> GLint { s.with_c_str(|ptr| unsafe { gl::GetUniformLocation(program, ptr) }) };
+    unsafe { "out_color".with_c_str(|ptr| gl::BindFragDataLocation(program, 0, ptr)) };
+
+    let position_attr = get_attrib("position");
+    let color_attr = get_attrib("color");
+    let time_uniform = get_uniform("time");
+
+    unsafe {
+        gl::EnableVertexAttribArray(position_attr as GLuint);
+        gl::EnableVertexAttribArray(color_attr as GLuint);
+        let stride = 5 * sizeof_float as GLsizei;
+        gl::VertexAttribPointer(position_attr as GLuint, 2, gl::FLOAT,
+                                gl::FALSE, stride, ptr::null());
+        gl::VertexAttribPointer(color_attr as GLuint, 3, gl::FLOAT,
+                                gl::FALSE, stride, ptr::null().offset(2 * sizeof_float as int));
+    }
+
+    let instances = instance_data(instance_count);
+    let offset_attr = get_attrib("instance_offset");
+    let angle_attr = get_attrib("instance_angle");
+
+    let mut instance_vbo = 0;
+    if !naive {
+        unsafe {
+            gl::GenBuffers(1, &mut instance_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,
+                           (instances.len() * sizeof_float) as GLsizeiptr,
+                           instances.as_ptr() as *GLvoid,
+                           gl::STATIC_DRAW);
+
+            gl::EnableVertexAttribArray(offset_attr as GLuint);
+            gl::EnableVertexAttribArray(angle_attr as GLuint);
+            let stride = 3 * sizeof_float as GLsizei;
+            gl::VertexAttribPointer(offset_attr as GLuint, 2, gl::FLOAT,
+                                    gl::FALSE, stride, ptr::null());
+            gl::VertexAttribPointer(angle_attr as GLuint, 1, gl::FLOAT,
+                                    gl::FALSE, stride, ptr::null().offset(2 * sizeof_float as int));
+            gl::VertexAttribDivisor(offset_attr as GLuint, 1);
+            gl::VertexAttribDivisor(angle_attr as GLuint, 1);
+        }
+    }
+
+    println!("benchmarking {} instances ({})", instance_count,
+             if naive { "naive per-instance DrawArrays" } else { "instanced DrawArraysInstanced" });
+
+    let mut frames = 0u;
+    let mut window_start = time::precise_time_s();
+    let start = time::precise_time_s();
+
+    while !window.should_close() {
+        for event in window.poll_events().into_iter() {
+            match event {
+                backend::WindowEvent::Key(backend::Key::Escape, backend::Action::Press) =>
+                    window.set_should_close(true),
+                _ => {},
+            }
+        }
+
+        gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+
+        let t = (time::precise_time_s() - start) as GLfloat;
+
+        if naive {
+            // One glDrawArrays call per instance: the baseline this mode
+            // measures against. `instance_offset` isn't a live array here
+            // (see above), so feed each instance's offset as the generic
+            // attribute's constant value, same trick as the `time` uniform.
+            for i in range(0u, instance_count) {
+                gl::Uniform1f(time_uniform, t + instances[i * 3 + 2]);
+                unsafe {
+                    gl::VertexAttrib2f(offset_attr as GLuint,
+                                       instances[i * 3], instances[i * 3 + 1]);
+                }
+                gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            }
+        } else {
+            gl::Uniform1f(time_uniform, t);
+            unsafe { gl::DrawArraysInstanced(gl::TRIANGLES, 0, 3, instance_count as GLsizei) };
+        }
+
+        window.swap_buffers();
+
+        frames += 1;
+        let now = time::precise_time_s();
+        if now - window_start >= 1.0 {
+            println!("{:.1} fps", frames as f64 / (now - window_start));
+            frames = 0;
+            window_start = now;
+        }
+    }
+
+    gl::DeleteProgram(program);
+    gl::DeleteShader(fs);
+    gl::DeleteShader(vs);
+    unsafe {
+        gl::DeleteBuffers(1, &triangle_vbo);
+        if !naive {
+            gl::DeleteBuffers(1, &instance_vbo);
+        }
+        gl::DeleteVertexArrays(1, &vao);
+    }
+}
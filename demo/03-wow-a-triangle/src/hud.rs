@@ -0,0 +1,222 @@
+// Copyright 2014 Brendan Zabarauskas.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A tiny text overlay: rasterizes glyphs with FreeType into single-channel
+//! textures, caches them per character, and blits them as alpha-blended
+//! quads over an orthographic projection. Drawn after the scene so it
+//! composites on top.
+
+use freetype;
+use gl;
+use gl::types::{GLfloat, GLint, GLsizei, GLsizeiptr, GLuint, GLvoid};
+use cgmath::projection::ortho;
+use std::collections::HashMap;
+use std::mem;
+use std::ptr;
+use super::{compile_shader, link_program, uniform_matrix4};
+
+static TEXT_VERTEX_SHADER_SRC: &'static [u8] = b"
+    #version 150
+    uniform mat4 projection;
+    in vec2 position;
+    in vec2 texcoord;
+    out vec2 in_texcoord;
+    void main() {
+       gl_Position = projection * vec4(position, 0.0, 1.0);
+       in_texcoord = texcoord;
+    }
+";
+
+static TEXT_FRAGMENT_SHADER_SRC: &'static [u8] = b"
+    #version 150
+    uniform sampler2D glyph;
+    uniform vec3 text_color;
+    in vec2 in_texcoord;
+    out vec4 out_color;
+    void main() {
+       float alpha = texture(glyph, in_texcoord).r;
+       out_color = vec4(text_color, alpha);
+    }
+";
+
+struct Glyph {
+    texture: GLuint,
+    width: GLfloat,
+    height: GLfloat,
+    bearing_x: GLfloat,
+    bearing_y: GLfloat,
+    advance: GLfloat,
+}
+
+/// Renders short strings (an FPS counter, rotation velocities) as a HUD
+/// overlay. Owns its own tiny GL program and glyph texture cache; entirely
+/// separate from the cube's shaders and VAO.
+pub struct Hud {
+    face: freetype::Face,
+    glyphs: HashMap<char, Glyph>,
+    program: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+    projection_uniform: GLint,
+    glyph_uniform: GLint,
+    color_uniform: GLint,
+}
+
+impl Hud {
+    /// Builds the HUD, or `Err` if the font asset couldn't be loaded. The
+    /// caller can treat that as non-fatal and simply run without a HUD.
+    pub fn new(font_path: &str, pixel_height: u32) -> Result<Hud, String> {
+        let library = freetype::Library::init()
+            .unwrap_or_else(|e| fail!("failed to initialise FreeType: {}", e));
+        let face = match library.new_face(font_path, 0) {
+            Ok(face) => face,
+            Err(e) => return Err(format!("failed to load font {}: {}", font_path, e)),
+        };
+        face.set_pixel_sizes(0, pixel_height)
+            .unwrap_or_else(|e| fail!("failed to set font size: {}", e));
+
+        let vs = compile_shader(TEXT_VERTEX_SHADER_SRC, gl::VERTEX_SHADER)
+            .unwrap_or_else(|log| fail!("hud vertex shader failed to compile:\n{}", log));
+        let fs = compile_shader(TEXT_FRAGMENT_SHADER_SRC, gl::FRAGMENT_SHADER)
+            .unwrap_or_else(|log| fail!("hud fragment shader failed to compile:\n{}", log));
+        let program = link_program(vs, fs)
+            .unwrap_or_else(|log| fail!("hud program failed to link:\n{}", log));
+        gl::DeleteShader(vs);
+        gl::DeleteShader(fs);
+
+        let mut vao = 0;
+        let mut vbo = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        }
+
+        gl::UseProgram(program);
+        unsafe { "out_color".with_c_str(|ptr| gl::BindFragDataLocation(program, 0, ptr)) };
+
+        let get_attrib = |s: &str| -> GLint { s.with_c_str(|ptr| unsafe { gl::GetAttribLocation(program, ptr) }) };
+        let get_uniform = |s: &str| -> GLint { s.with_c_str(|ptr| unsafe { gl::GetUniformLocation(program, ptr) }) };
+        let position_attr = get_attrib("position");
+        let texcoord_attr = get_attrib("texcoord");
+
+        unsafe {
+            gl::EnableVertexAttribArray(position_attr as GLuint);
+            gl::EnableVertexAttribArray(texcoord_attr as GLuint);
+            let sizeof_float = mem::size_of::<GLfloat>();
+            let stride = 4 * sizeof_float as GLsizei;
+            gl::VertexAttribPointer(position_attr as GLuint, 2, gl::FLOAT,
+                                    gl::FALSE, stride, ptr::null());
+            gl::VertexAttribPointer(texcoord_attr as GLuint, 2, gl::FLOAT,
+                                    gl::FALSE, stride, ptr::null().offset(2 * sizeof_float as int));
+        }
+
+        Ok(Hud {
+            face: face,
+            glyphs: HashMap::new(),
+            program: program,
+            vao: vao,
+            vbo: vbo,
+            projection_uniform: get_uniform("projection"),
+            glyph_uniform: get_uniform("glyph"),
+            color_uniform: get_uniform("text_color"),
+        })
+    }
+
+    fn glyph(&mut self, c: char) -> &Glyph {
+        if !self.glyphs.contains_key(&c) {
+            self.glyphs.insert(c, load_glyph(&self.face, c));
+        }
+        self.glyphs.get(&c).unwrap()
+    }
+
+    /// Draws `text` starting at `(x, y)` in screen pixels, where `y` is the
+    /// baseline: each glyph rises above it by its bearing.
+    pub fn draw_text(&mut self, text: &str, x: GLfloat, y: GLfloat,
+                      screen_width: u32, screen_height: u32) {
+        gl::Enable(gl::BLEND);
+        unsafe { gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA) };
+
+        gl::UseProgram(self.program);
+        gl::BindVertexArray(self.vao);
+
+        let projection = ortho(0.0, screen_width as GLfloat,
+                               screen_height as GLfloat, 0.0, -1.0, 1.0);
+        uniform_matrix4(self.projection_uniform, &projection);
+        gl::Uniform3f(self.color_uniform, 1.0, 1.0, 1.0);
+        gl::Uniform1i(self.glyph_uniform, 0);
+        unsafe { gl::ActiveTexture(gl::TEXTURE0) };
+
+        let mut pen_x = x;
+        for c in text.chars() {
+            let glyph = self.glyph(c);
+
+            let x0 = pen_x + glyph.bearing_x;
+            let y0 = y - glyph.bearing_y;
+            let quad: [GLfloat, ..24] = [
+                x0,                y0,                0.0, 0.0,
+                x0,                y0 + glyph.height,  0.0, 1.0,
+                x0 + glyph.width,  y0 + glyph.height,  1.0, 1.0,
+
+                x0,                y0,                0.0, 0.0,
+                x0 + glyph.width,  y0 + glyph.height,  1.0, 1.0,
+                x0 + glyph.width,  y0,                1.0, 0.0,
+            ];
+
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, glyph.texture);
+                gl::BufferData(gl::ARRAY_BUFFER,
+                               (quad.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                               quad.as_ptr() as *GLvoid,
+                               gl::DYNAMIC_DRAW);
+            }
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+            pen_x += glyph.advance;
+        }
+
+        gl::Disable(gl::BLEND);
+    }
+}
+
+fn load_glyph(face: &freetype::Face, c: char) -> Glyph {
+    face.load_char(c as uint, freetype::face::RENDER)
+        .unwrap_or_else(|e| fail!("failed to rasterize glyph {}: {}", c, e));
+    let glyph = face.glyph();
+    let bitmap = glyph.bitmap();
+
+    let mut texture = 0;
+    unsafe {
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+        gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RED as GLint,
+                       bitmap.width(), bitmap.rows(), 0,
+                       gl::RED, gl::UNSIGNED_BYTE, bitmap.buffer().as_ptr() as *GLvoid);
+    }
+
+    Glyph {
+        texture: texture,
+        width: bitmap.width() as GLfloat,
+        height: bitmap.rows() as GLfloat,
+        bearing_x: glyph.bitmap_left() as GLfloat,
+        bearing_y: glyph.bitmap_top() as GLfloat,
+        advance: (glyph.advance().x >> 6) as GLfloat,
+    }
+}